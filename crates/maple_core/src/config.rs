@@ -1,18 +1,385 @@
+use arc_swap::ArcSwap;
 use dirs::Dirs;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use once_cell::sync::OnceCell;
 use paths::AbsPathBuf;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 use types::RankCriterion;
 
 static CONFIG_FILE: OnceCell<PathBuf> = OnceCell::new();
-// TODO: reload-config
-static CONFIG: OnceCell<Config> = OnceCell::new();
+static CONFIG: OnceCell<ArcSwap<Config>> = OnceCell::new();
+// Parse error from the most recent reload attempt, if any. The last-good
+// `Config` in `CONFIG` is left untouched so a typo in config.toml can't
+// take down an already-running session.
+static CONFIG_RELOAD_ERROR: OnceCell<Mutex<Option<String>>> = OnceCell::new();
+// Diagnostics from the most recent (successful or not) parse of config.toml.
+static CONFIG_DIAGNOSTICS: OnceCell<Mutex<Vec<ConfigDiagnostic>>> = OnceCell::new();
+type ConfigSubscriber = Box<dyn Fn(&Config) + Send + Sync>;
+static CONFIG_SUBSCRIBERS: OnceCell<Mutex<Vec<ConfigSubscriber>>> = OnceCell::new();
+
+/// Parses `config_file`, falling back to `Config::default()` when the file
+/// is missing or fails to deserialize. The deserialize error, if any, is
+/// returned alongside so the caller can decide how to surface it, together
+/// with a best-effort list of actionable diagnostics (typo'd keys, invalid
+/// values) produced by walking the raw TOML against the known schema.
+fn parse_config_file(
+    config_file: &Path,
+) -> (Config, Option<toml::de::Error>, Vec<ConfigDiagnostic>) {
+    let contents = std::fs::read_to_string(config_file).unwrap_or_default();
+
+    let diagnostics = diagnose(&contents);
+
+    let mut maybe_config_err = None;
+    let loaded_config = match toml::from_str(&contents) {
+        Ok(config) => config,
+        Err(err) => {
+            maybe_config_err.replace(err);
+            Config::default()
+        }
+    };
+
+    (loaded_config, maybe_config_err, diagnostics)
+}
+
+/// One actionable problem found in `config.toml`, surfaced instead of
+/// silently falling back to `Config::default()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigDiagnostic {
+    /// Dotted path to the offending key, e.g. `matcher.tiebrake`.
+    pub path: String,
+    /// Human-readable explanation, e.g. "did you mean `tiebreak`?".
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Maximum edit distance for a key typo to be suggested as-is.
+const SUGGESTION_MAX_DISTANCE: usize = 2;
+
+/// Parses `contents` as a generic [`toml::Value`] and walks it against the
+/// known schema, reporting unknown keys (with a nearest-match suggestion)
+/// and semantically invalid values. Unlike `toml::from_str::<Config>`, this
+/// never bails out early on the first error, so a single pass surfaces
+/// every mistake in the file.
+fn diagnose(contents: &str) -> Vec<ConfigDiagnostic> {
+    let mut diagnostics = Vec::new();
+
+    let Ok(toml::Value::Table(root)) = contents.parse::<toml::Value>() else {
+        return diagnostics;
+    };
+
+    check_table("", &root, TOP_LEVEL_KEYS, &mut diagnostics);
+
+    if let Some(toml::Value::Table(log)) = root.get("log") {
+        check_table("log", log, LOG_KEYS, &mut diagnostics);
+
+        if let Some(toml::Value::Table(rotation)) = log.get("rotation") {
+            check_table("log.rotation", rotation, LOG_ROTATION_KEYS, &mut diagnostics);
+        }
+    }
+
+    if let Some(toml::Value::Table(matcher)) = root.get("matcher") {
+        check_table("matcher", matcher, MATCHER_KEYS, &mut diagnostics);
+    }
+
+    if let Some(toml::Value::Table(global_ignore)) = root.get("global-ignore") {
+        check_table("global-ignore", global_ignore, IGNORE_KEYS, &mut diagnostics);
+        validate_ignore_patterns("global-ignore", global_ignore, &mut diagnostics);
+    }
+
+    if let Some(toml::Value::Table(plugin)) = root.get("plugin") {
+        check_table("plugin", plugin, PLUGIN_KEYS, &mut diagnostics);
+        for (name, keys) in [
+            ("colorizer", ENABLE_ONLY_KEYS),
+            ("cursorword", CURSORWORD_KEYS),
+            ("ctags", CTAGS_KEYS),
+            ("git", GIT_KEYS),
+            ("linter", ENABLE_ONLY_KEYS),
+            ("markdown", ENABLE_ONLY_KEYS),
+            ("syntax", SYNTAX_KEYS),
+        ] {
+            if let Some(toml::Value::Table(table)) = plugin.get(name) {
+                check_table(&format!("plugin.{name}"), table, keys, &mut diagnostics);
+            }
+        }
+
+        if let Some(toml::Value::Table(strategy)) = plugin
+            .get("syntax")
+            .and_then(|syntax| syntax.get("render-strategy"))
+        {
+            check_table(
+                "plugin.syntax.render-strategy",
+                strategy,
+                RENDER_STRATEGY_KEYS,
+                &mut diagnostics,
+            );
+        }
+    }
+
+    if let Some(toml::Value::Table(provider)) = root.get("provider") {
+        check_table("provider", provider, PROVIDER_KEYS, &mut diagnostics);
+
+        for map_key in ["provider-ignores", "project-ignores"] {
+            if let Some(toml::Value::Table(entries)) = provider.get(map_key) {
+                for (id, value) in entries {
+                    if let toml::Value::Table(ignore) = value {
+                        let path_prefix = format!("provider.{map_key}.{id}");
+                        check_table(&path_prefix, ignore, IGNORE_KEYS, &mut diagnostics);
+                        validate_ignore_patterns(&path_prefix, ignore, &mut diagnostics);
+                    }
+                }
+            }
+        }
+
+        if let Some(toml::Value::Table(entries)) = provider.get("preview") {
+            for (id, value) in entries {
+                if let toml::Value::Table(preview) = value {
+                    check_table(
+                        &format!("provider.preview.{id}"),
+                        preview,
+                        PROVIDER_PREVIEW_KEYS,
+                        &mut diagnostics,
+                    );
+                }
+            }
+        }
+    }
+
+    validate_semantics(&root, &mut diagnostics);
+
+    diagnostics
+}
+
+const TOP_LEVEL_KEYS: &[&str] = &["log", "matcher", "plugin", "provider", "global-ignore"];
+const LOG_KEYS: &[&str] = &["log-file", "max-level", "log-target", "rotation"];
+const LOG_ROTATION_KEYS: &[&str] = &["trigger", "max-size", "max-archived-files", "compress"];
+const MATCHER_KEYS: &[&str] = &["tiebreak"];
+const IGNORE_KEYS: &[&str] = &[
+    "ignore-comments",
+    "git-tracked-only",
+    "ignore-file-name-pattern",
+    "ignore-file-path-pattern",
+];
+const PLUGIN_KEYS: &[&str] = &[
+    "colorizer",
+    "cursorword",
+    "ctags",
+    "git",
+    "linter",
+    "markdown",
+    "syntax",
+];
+const ENABLE_ONLY_KEYS: &[&str] = &["enable"];
+const CURSORWORD_KEYS: &[&str] = &["enable", "ignore-comment-line", "ignore-files"];
+const CTAGS_KEYS: &[&str] = &["enable", "max-file-size"];
+const GIT_KEYS: &[&str] = &["enable", "blame-format-string"];
+const SYNTAX_KEYS: &[&str] = &["render-strategy"];
+const RENDER_STRATEGY_KEYS: &[&str] = &["strategy", "file-size-limit"];
+const PROVIDER_KEYS: &[&str] = &[
+    "share-input-history",
+    "max-display-size",
+    "preview-highlight-engine",
+    "sublime-syntax-color-scheme",
+    "project-ignores",
+    "provider-ignores",
+    "debounce",
+    "preview",
+];
+const PROVIDER_PREVIEW_KEYS: &[&str] = &[
+    "preview-highlight-engine",
+    "color-mode",
+    "dark-color-scheme",
+    "light-color-scheme",
+];
+
+/// Reports every key in `table` that isn't in `known_keys`, suggesting the
+/// closest known key when its edit distance is small enough to plausibly
+/// be a typo.
+fn check_table(
+    path_prefix: &str,
+    table: &toml::map::Map<String, toml::Value>,
+    known_keys: &[&str],
+    diagnostics: &mut Vec<ConfigDiagnostic>,
+) {
+    for key in table.keys() {
+        if known_keys.contains(&key.as_str()) {
+            continue;
+        }
+
+        let suggestion = known_keys
+            .iter()
+            .map(|candidate| (*candidate, levenshtein_distance(key, candidate)))
+            .min_by_key(|(_, distance)| *distance)
+            .filter(|(_, distance)| *distance <= SUGGESTION_MAX_DISTANCE)
+            .map(|(candidate, _)| candidate);
+
+        let message = match suggestion {
+            Some(suggestion) => format!("unknown key `{key}`, did you mean `{suggestion}`?"),
+            None => format!("unknown key `{key}`"),
+        };
+
+        let path = if path_prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{path_prefix}.{key}")
+        };
+
+        diagnostics.push(ConfigDiagnostic { path, message });
+    }
+}
+
+/// Validates values that `serde`'s type-level deserialization can't catch,
+/// such as a `tiebreak` criterion that doesn't exist or a log level that
+/// isn't one of the handful `tracing` understands.
+fn validate_semantics(
+    root: &toml::map::Map<String, toml::Value>,
+    diagnostics: &mut Vec<ConfigDiagnostic>,
+) {
+    const VALID_LOG_LEVELS: &[&str] = &["trace", "debug", "info", "warn", "error"];
+    if let Some(max_level) = root
+        .get("log")
+        .and_then(|log| log.get("max-level"))
+        .and_then(toml::Value::as_str)
+    {
+        if !VALID_LOG_LEVELS.contains(&max_level) {
+            diagnostics.push(ConfigDiagnostic {
+                path: "log.max-level".into(),
+                message: format!(
+                    "invalid log level `{max_level}`, expected one of {VALID_LOG_LEVELS:?}"
+                ),
+            });
+        }
+    }
+
+    if let Some(max_size) = root
+        .get("log")
+        .and_then(|log| log.get("rotation"))
+        .and_then(|rotation| rotation.get("max-size"))
+        .and_then(toml::Value::as_str)
+    {
+        if let Err(err) = parse_human_size(max_size) {
+            diagnostics.push(ConfigDiagnostic {
+                path: "log.rotation.max-size".into(),
+                message: err,
+            });
+        }
+    }
+
+    if let Some(tiebreak) = root
+        .get("matcher")
+        .and_then(|matcher| matcher.get("tiebreak"))
+        .and_then(toml::Value::as_str)
+    {
+        for token in tiebreak.split(',') {
+            let token = token.trim();
+            if types::parse_criteria(token).is_none() {
+                diagnostics.push(ConfigDiagnostic {
+                    path: "matcher.tiebreak".into(),
+                    message: format!("unrecognized tiebreak criterion `{token}`"),
+                });
+            }
+        }
+    }
+
+    const VALID_RENDER_STRATEGIES: &[&str] = &["visual-lines", "entire-buffer-up-to-limit"];
+    if let Some(strategy) = root
+        .get("plugin")
+        .and_then(|plugin| plugin.get("syntax"))
+        .and_then(|syntax| syntax.get("render-strategy"))
+        .and_then(|render_strategy| render_strategy.get("strategy"))
+        .and_then(toml::Value::as_str)
+    {
+        if !VALID_RENDER_STRATEGIES.contains(&strategy) {
+            diagnostics.push(ConfigDiagnostic {
+                path: "plugin.syntax.render-strategy.strategy".into(),
+                message: format!(
+                    "unknown render strategy `{strategy}`, expected one of {VALID_RENDER_STRATEGIES:?}"
+                ),
+            });
+        }
+    }
+
+    const ABSURD_DEBOUNCE_MS: i64 = 60_000;
+    if let Some(toml::Value::Table(debounce)) =
+        root.get("provider").and_then(|provider| provider.get("debounce"))
+    {
+        for (provider_id, value) in debounce {
+            if let Some(ms) = value.as_integer() {
+                if ms > ABSURD_DEBOUNCE_MS {
+                    diagnostics.push(ConfigDiagnostic {
+                        path: format!("provider.debounce.{provider_id}"),
+                        message: format!(
+                            "debounce of {ms}ms is unusually large, is this intentional?"
+                        ),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Reports every pattern in `ignore-file-name-pattern` / `ignore-file-path-pattern`
+/// that isn't a valid glob, naming the offending pattern.
+fn validate_ignore_patterns(
+    path_prefix: &str,
+    table: &toml::map::Map<String, toml::Value>,
+    diagnostics: &mut Vec<ConfigDiagnostic>,
+) {
+    for key in ["ignore-file-name-pattern", "ignore-file-path-pattern"] {
+        let Some(toml::Value::Array(patterns)) = table.get(key) else {
+            continue;
+        };
+
+        for pattern in patterns.iter().filter_map(toml::Value::as_str) {
+            if let Err(err) = globset::Glob::new(pattern) {
+                diagnostics.push(ConfigDiagnostic {
+                    path: format!("{path_prefix}.{key}"),
+                    message: format!("invalid glob pattern `{pattern}`: {err}"),
+                });
+            }
+        }
+    }
+}
+
+/// Classic Wagner-Fischer edit distance, used to find the valid key that
+/// most plausibly matches a typo'd one.
+fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut prev = row[0];
+        row[0] = i;
+        for j in 1..=b.len() {
+            let tmp = row[j];
+            row[j] = if a[i - 1] == b[j - 1] {
+                prev
+            } else {
+                1 + prev.min(row[j]).min(row[j - 1])
+            };
+            prev = tmp;
+        }
+    }
+    row[b.len()]
+}
 
 pub fn load_config_on_startup(
     specified_config_file: Option<PathBuf>,
-) -> (&'static Config, Option<toml::de::Error>) {
+) -> (Arc<Config>, Option<toml::de::Error>, Vec<ConfigDiagnostic>) {
     let config_file = specified_config_file.unwrap_or_else(|| {
         // Linux: ~/.config/vimclap/config.toml
         // macOS: ~/Library/Application\ Support/org.vim.Vim-Clap/config.toml
@@ -26,35 +393,354 @@ pub fn load_config_on_startup(
         config_file_path
     });
 
-    let mut maybe_config_err = None;
-    let loaded_config = std::fs::read_to_string(&config_file)
-        .and_then(|contents| {
-            toml::from_str(&contents).map_err(|err| {
-                maybe_config_err.replace(err);
-                std::io::Error::new(std::io::ErrorKind::Other, "Error occurred in config.toml")
-            })
-        })
-        .unwrap_or_default();
+    let (loaded_config, maybe_config_err, diagnostics) = parse_config_file(&config_file);
 
     CONFIG_FILE
-        .set(config_file)
+        .set(config_file.clone())
         .expect("Failed to initialize Config file");
 
     CONFIG
-        .set(loaded_config)
+        .set(ArcSwap::from_pointee(loaded_config))
         .expect("Failed to initialize Config");
 
-    (config(), maybe_config_err)
+    CONFIG_DIAGNOSTICS
+        .set(Mutex::new(diagnostics.clone()))
+        .expect("Failed to initialize Config diagnostics");
+
+    watch_config_file(config_file);
+
+    (config(), maybe_config_err, diagnostics)
 }
 
-pub fn config() -> &'static Config {
-    CONFIG.get().expect("Config must be initialized")
+/// Returns a cheap, consistent snapshot of the current config.
+///
+/// Hold on to this `Arc` for the duration of a single request rather than
+/// calling `config()` repeatedly, otherwise a reload racing with the
+/// request could produce inconsistent reads (e.g. mismatched `tiebreak`
+/// and `debounce` values).
+pub fn config() -> Arc<Config> {
+    CONFIG
+        .get()
+        .expect("Config must be initialized")
+        .load_full()
 }
 
 pub fn config_file() -> &'static PathBuf {
     CONFIG_FILE.get().expect("Config file uninitialized")
 }
 
+/// Returns the error from the most recent failed reload attempt, if any.
+pub fn config_reload_error() -> Option<String> {
+    CONFIG_RELOAD_ERROR
+        .get_or_init(|| Mutex::new(None))
+        .lock()
+        .unwrap()
+        .clone()
+}
+
+/// Returns the diagnostics (typo'd keys, invalid values) found in the most
+/// recently (re)loaded `config.toml`.
+pub fn config_diagnostics() -> Vec<ConfigDiagnostic> {
+    CONFIG_DIAGNOSTICS
+        .get_or_init(|| Mutex::new(Vec::new()))
+        .lock()
+        .unwrap()
+        .clone()
+}
+
+/// Appends a single diagnostic (e.g. a bad project-local config file) to
+/// the diagnostics surfaced by `config_diagnostics()`, without discarding
+/// whatever was already recorded for `config.toml` itself.
+fn push_config_diagnostic(diagnostic: ConfigDiagnostic) {
+    CONFIG_DIAGNOSTICS
+        .get_or_init(|| Mutex::new(Vec::new()))
+        .lock()
+        .unwrap()
+        .push(diagnostic);
+}
+
+/// Registers a callback invoked with the new config every time it's
+/// reloaded from disk. Useful for plugins that otherwise only read
+/// `enable` once at startup.
+pub fn subscribe(f: impl Fn(&Config) + Send + Sync + 'static) {
+    CONFIG_SUBSCRIBERS
+        .get_or_init(|| Mutex::new(Vec::new()))
+        .lock()
+        .unwrap()
+        .push(Box::new(f));
+}
+
+/// Re-reads and re-parses `config_file`, atomically swapping in the new
+/// `Config` on success. On failure the last-good config is left in place
+/// and the error is recorded for `config_reload_error()`.
+fn reload_config(config_file: &Path) {
+    let (new_config, maybe_config_err, diagnostics) = parse_config_file(config_file);
+
+    *CONFIG_DIAGNOSTICS
+        .get_or_init(|| Mutex::new(Vec::new()))
+        .lock()
+        .unwrap() = diagnostics;
+
+    match maybe_config_err {
+        Some(err) => {
+            tracing::error!(
+                ?err,
+                "Failed to reload config.toml, keeping the last-good config"
+            );
+            *CONFIG_RELOAD_ERROR.get_or_init(|| Mutex::new(None)).lock().unwrap() =
+                Some(err.to_string());
+        }
+        None => {
+            tracing::debug!("Config reloaded from {}", config_file.display());
+            *CONFIG_RELOAD_ERROR.get_or_init(|| Mutex::new(None)).lock().unwrap() = None;
+
+            CONFIG
+                .get()
+                .expect("Config must be initialized")
+                .store(Arc::new(new_config));
+
+            if let Some(subscribers) = CONFIG_SUBSCRIBERS.get() {
+                let current = config();
+                for subscriber in subscribers.lock().unwrap().iter() {
+                    subscriber(&current);
+                }
+            }
+        }
+    }
+}
+
+/// Spawns a background thread watching `config_file` for modifications and
+/// debouncing bursts of events before triggering a reload.
+fn watch_config_file(config_file: PathBuf) {
+    watch_file(config_file.clone(), move || reload_config(&config_file));
+}
+
+/// Spawns a background thread that calls `on_change` (debounced) whenever
+/// `watched_file` is modified, created, or removed.
+///
+/// This watches `watched_file`'s *parent directory* rather than the file
+/// itself: editors commonly save via write-then-rename, which replaces the
+/// watched inode. A watch attached directly to the file path would silently
+/// stop firing after the first such save (or fail to attach at all if the
+/// file doesn't exist yet), so the directory is watched instead and events
+/// are filtered down to the one path we care about.
+fn watch_file(watched_file: PathBuf, on_change: impl Fn() + Send + 'static) {
+    use notify::{RecursiveMode, Watcher};
+
+    std::thread::spawn(move || {
+        let Some(parent) = watched_file.parent() else {
+            tracing::error!("Cannot watch {}: it has no parent directory", watched_file.display());
+            return;
+        };
+
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        let mut watcher = match notify::recommended_watcher(tx) {
+            Ok(watcher) => watcher,
+            Err(err) => {
+                tracing::error!(?err, "Failed to create file watcher");
+                return;
+            }
+        };
+
+        if let Err(err) = watcher.watch(parent, RecursiveMode::NonRecursive) {
+            tracing::error!(?err, "Failed to watch directory {}", parent.display());
+            return;
+        }
+
+        const DEBOUNCE: Duration = Duration::from_millis(300);
+        let mut pending_reload = false;
+        loop {
+            match rx.recv_timeout(DEBOUNCE) {
+                Ok(Ok(event))
+                    if !event.kind.is_access()
+                        && event.paths.iter().any(|path| *path == watched_file) =>
+                {
+                    pending_reload = true;
+                }
+                Ok(Ok(_)) => {}
+                Ok(Err(err)) => tracing::error!(?err, "File watcher error"),
+                Err(RecvTimeoutError::Timeout) => {
+                    if pending_reload {
+                        pending_reload = false;
+                        on_change();
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    });
+}
+
+/// Name of the project-local config file, discovered by walking up from a
+/// provider's working directory.
+const PROJECT_LOCAL_CONFIG_FILE_NAME: &str = ".vimclap.toml";
+
+/// Keys whose arrays are concatenated (global first, local appended)
+/// rather than replaced wholesale when merging project-local config onto
+/// the global one. Everything else either recurses into nested tables or
+/// has the local scalar/map entry win.
+const CONCAT_ARRAY_KEYS: &[&str] = &["ignore-file-name-pattern", "ignore-file-path-pattern"];
+
+static PROJECT_CONFIG_CACHE: OnceCell<Mutex<HashMap<PathBuf, Arc<Config>>>> = OnceCell::new();
+static PROJECT_CONFIG_CACHE_INIT: OnceCell<()> = OnceCell::new();
+static WATCHED_PROJECT_CONFIG_FILES: OnceCell<Mutex<HashSet<PathBuf>>> = OnceCell::new();
+
+/// Returns the effective config for `project_dir`: the global config with
+/// the nearest `.vimclap.toml` (if any) found by walking up from
+/// `project_dir` to the filesystem root or the enclosing git root merged
+/// on top. Results are cached per project directory and invalidated
+/// whenever the global config is reloaded, or the discovered
+/// `.vimclap.toml` itself changes on disk.
+pub fn config_for_project(project_dir: &Path) -> Arc<Config> {
+    PROJECT_CONFIG_CACHE_INIT.get_or_init(|| subscribe(|_| invalidate_project_config_cache()));
+
+    let cache = PROJECT_CONFIG_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some(cached) = cache.lock().unwrap().get(project_dir) {
+        return Arc::clone(cached);
+    }
+
+    let merged = match discover_project_config_file(project_dir) {
+        Some(local_config_file) => {
+            watch_project_config_file_once(&local_config_file);
+
+            match merge_project_config(&config(), &local_config_file) {
+                Ok(merged) => Arc::new(merged),
+                Err(err) => {
+                    tracing::error!(
+                        %err,
+                        "Failed to merge {}, falling back to the global config",
+                        local_config_file.display()
+                    );
+                    push_config_diagnostic(ConfigDiagnostic {
+                        path: local_config_file.display().to_string(),
+                        message: err,
+                    });
+                    config()
+                }
+            }
+        }
+        None => {
+            // No local config file exists yet anywhere from `project_dir`
+            // up to the git/filesystem root. Watch `project_dir` itself so
+            // creating one later still invalidates this cache entry,
+            // rather than requiring an unrelated global reload or restart.
+            watch_project_config_file_once(&project_dir.join(PROJECT_LOCAL_CONFIG_FILE_NAME));
+            config()
+        }
+    };
+
+    cache
+        .lock()
+        .unwrap()
+        .insert(project_dir.to_path_buf(), Arc::clone(&merged));
+
+    merged
+}
+
+fn invalidate_project_config_cache() {
+    if let Some(cache) = PROJECT_CONFIG_CACHE.get() {
+        cache.lock().unwrap().clear();
+    }
+}
+
+/// Starts watching `local_config_file` for changes, invalidating the
+/// project config cache on edit, the first time a given path is seen. A
+/// project dir is merged (and its local config file discovered) lazily on
+/// first access, so this is where the watch gets attached rather than at
+/// startup.
+fn watch_project_config_file_once(local_config_file: &Path) {
+    let watched = WATCHED_PROJECT_CONFIG_FILES.get_or_init(|| Mutex::new(HashSet::new()));
+    if !watched.lock().unwrap().insert(local_config_file.to_path_buf()) {
+        return;
+    }
+
+    watch_file(local_config_file.to_path_buf(), invalidate_project_config_cache);
+}
+
+/// Walks up from `start_dir` looking for [`PROJECT_LOCAL_CONFIG_FILE_NAME`],
+/// stopping at the filesystem root or as soon as a `.git` directory is
+/// passed (so a monorepo subproject doesn't pick up an unrelated parent
+/// repo's file).
+fn discover_project_config_file(start_dir: &Path) -> Option<PathBuf> {
+    let mut dir = Some(start_dir);
+    while let Some(current) = dir {
+        let candidate = current.join(PROJECT_LOCAL_CONFIG_FILE_NAME);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+
+        if current.join(".git").exists() {
+            return None;
+        }
+
+        dir = current.parent();
+    }
+
+    None
+}
+
+/// Reads `local_config_file` and deep-merges it onto `global`: scalar
+/// fields are overridden by the local value, the `HashMap` fields
+/// (`debounce`, `provider_ignores`, `project_ignores`) are unioned with
+/// local keys taking precedence, and the `IgnoreConfig` pattern vectors
+/// are concatenated rather than replaced.
+///
+/// On any failure (the file can't be read, isn't valid TOML, or the merged
+/// result no longer matches the `Config` schema) this returns an error
+/// describing what went wrong instead of falling back to `Config::default`:
+/// a bad local file should only cost the local override, not the
+/// already-valid global config underneath it.
+fn merge_project_config(global: &Config, local_config_file: &Path) -> Result<Config, String> {
+    let mut merged = toml::Value::try_from(global)
+        .map_err(|err| format!("failed to serialize the global config for merging: {err}"))?;
+
+    let contents = std::fs::read_to_string(local_config_file)
+        .map_err(|err| format!("failed to read {}: {err}", local_config_file.display()))?;
+    let local = contents
+        .parse::<toml::Value>()
+        .map_err(|err| format!("failed to parse {}: {err}", local_config_file.display()))?;
+
+    deep_merge_toml(&mut merged, local);
+
+    merged.try_into().map_err(|err| {
+        format!(
+            "{} doesn't match the expected config schema: {err}",
+            local_config_file.display()
+        )
+    })
+}
+
+fn deep_merge_toml(base: &mut toml::Value, overlay: toml::Value) {
+    match overlay {
+        toml::Value::Table(overlay_table) => {
+            if !matches!(base, toml::Value::Table(_)) {
+                *base = toml::Value::Table(Default::default());
+            }
+            let toml::Value::Table(base_table) = base else {
+                unreachable!("just normalized to a table above")
+            };
+
+            for (key, overlay_value) in overlay_table {
+                match (base_table.get_mut(&key), overlay_value) {
+                    (Some(toml::Value::Array(base_arr)), toml::Value::Array(mut overlay_arr))
+                        if CONCAT_ARRAY_KEYS.contains(&key.as_str()) =>
+                    {
+                        base_arr.append(&mut overlay_arr);
+                    }
+                    (Some(base_value), overlay_value) => {
+                        deep_merge_toml(base_value, overlay_value)
+                    }
+                    (None, overlay_value) => {
+                        base_table.insert(key, overlay_value);
+                    }
+                }
+            }
+        }
+        overlay => *base = overlay,
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
 #[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
 pub struct MatcherConfig {
@@ -99,6 +785,18 @@ pub struct LogConfig {
     /// log-target = "maple_core::stdio_server=trace,rpc=debug"
     /// ```
     pub log_target: String,
+
+    /// Rolling log-file policy. When unset, `log_file` grows unbounded,
+    /// matching the historical behavior.
+    ///
+    /// ```toml
+    /// [log.rotation]
+    /// trigger = "max-size"
+    /// max-size = "10MiB"
+    /// max-archived-files = 5
+    /// compress = true
+    /// ```
+    pub rotation: Option<LogRotationConfig>,
 }
 
 impl Default for LogConfig {
@@ -107,8 +805,299 @@ impl Default for LogConfig {
             log_file: None,
             max_level: "debug".into(),
             log_target: "".into(),
+            rotation: None,
+        }
+    }
+}
+
+/// Rolling log-file policy for [`LogConfig`], internally tagged on
+/// `trigger` so `max-archived-files` and `compress` sit alongside it in
+/// the same `[log.rotation]` table.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(tag = "trigger", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum LogRotationConfig {
+    /// Rotate once the active log file exceeds this size, e.g. `"10MiB"`.
+    /// Parsed with [`parse_human_size`].
+    #[serde(rename_all = "kebab-case")]
+    MaxSize {
+        #[serde(rename = "max-size")]
+        max_size: String,
+        /// Number of rotated (and possibly compressed) log files to keep
+        /// before the oldest is deleted. `None` keeps them all.
+        #[serde(default)]
+        max_archived_files: Option<usize>,
+        /// Whether to gzip rotated files.
+        #[serde(default)]
+        compress: bool,
+    },
+
+    /// Rotate at the start of each day.
+    #[serde(rename_all = "kebab-case")]
+    Daily {
+        #[serde(default)]
+        max_archived_files: Option<usize>,
+        #[serde(default)]
+        compress: bool,
+    },
+
+    /// Rotate at the start of each hour.
+    #[serde(rename_all = "kebab-case")]
+    Hourly {
+        #[serde(default)]
+        max_archived_files: Option<usize>,
+        #[serde(default)]
+        compress: bool,
+    },
+}
+
+impl LogRotationConfig {
+    /// Resolves the `max-size` trigger's human-readable size into bytes.
+    /// Time-based triggers have nothing to parse and return `None`.
+    pub fn max_size_bytes(&self) -> Option<Result<u64, String>> {
+        match self {
+            Self::MaxSize { max_size, .. } => Some(parse_human_size(max_size)),
+            Self::Daily { .. } | Self::Hourly { .. } => None,
+        }
+    }
+
+    pub fn max_archived_files(&self) -> Option<usize> {
+        match self {
+            Self::MaxSize {
+                max_archived_files, ..
+            }
+            | Self::Daily {
+                max_archived_files, ..
+            }
+            | Self::Hourly {
+                max_archived_files, ..
+            } => *max_archived_files,
+        }
+    }
+
+    pub fn compress(&self) -> bool {
+        match self {
+            Self::MaxSize { compress, .. }
+            | Self::Daily { compress, .. }
+            | Self::Hourly { compress, .. } => *compress,
+        }
+    }
+
+    /// Returns the path a rotated log file should take, given the active
+    /// `log_file` and a 1-based `index` (the lowest index is the most
+    /// recently rotated file), e.g. `clap.log.1`, or `clap.log.1.gz` when
+    /// `compress` is set.
+    pub fn rotated_file_name(&self, log_file: &Path, index: usize) -> PathBuf {
+        let mut rotated = log_file.as_os_str().to_os_string();
+        rotated.push(format!(".{index}"));
+        if self.compress() {
+            rotated.push(".gz");
+        }
+        PathBuf::from(rotated)
+    }
+
+    /// Given the indices of archived files currently present on disk,
+    /// returns the indices that exceed `max_archived_files` and should be
+    /// deleted: the largest, i.e. oldest, indices, in ascending order.
+    pub fn archived_files_to_prune(&self, mut present_indices: Vec<usize>) -> Vec<usize> {
+        let Some(max) = self.max_archived_files() else {
+            return Vec::new();
+        };
+
+        if present_indices.len() <= max {
+            return Vec::new();
+        }
+
+        present_indices.sort_unstable();
+        present_indices.split_off(max)
+    }
+}
+
+impl LogConfig {
+    /// Opens `log_file` for appending, wrapped in a [`RollingLogWriter`]
+    /// that enforces `rotation` on every write. Whatever initializes the
+    /// `tracing` subscriber should pass this (not a bare `File`) to
+    /// `with_writer` so `log_file` stays bounded. Returns `None` when no
+    /// `log_file` is configured, matching the historical "no file logging"
+    /// behavior.
+    pub fn open_writer(&self) -> Option<io::Result<RollingLogWriter>> {
+        let log_file = self.log_file.as_ref()?;
+        Some(RollingLogWriter::open(
+            PathBuf::from(log_file),
+            self.rotation.clone(),
+        ))
+    }
+}
+
+/// A [`Write`] implementation for [`LogConfig::log_file`] that consults the
+/// configured [`LogRotationConfig`] on every write: once the active trigger
+/// (size or day/hour boundary) is hit, the current file is rotated out
+/// (renamed, gzipped when `compress` is set), archives beyond
+/// `max-archived-files` are pruned, and a fresh file is opened at the
+/// original `log_file` path, which always holds the active log.
+pub struct RollingLogWriter {
+    log_file: PathBuf,
+    rotation: Option<LogRotationConfig>,
+    file: File,
+    size: u64,
+    opened_at: SystemTime,
+}
+
+impl RollingLogWriter {
+    pub fn open(log_file: PathBuf, rotation: Option<LogRotationConfig>) -> io::Result<Self> {
+        if let Some(parent) = log_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&log_file)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            log_file,
+            rotation,
+            file,
+            size,
+            opened_at: SystemTime::now(),
+        })
+    }
+
+    fn should_rotate(&self, incoming: usize) -> bool {
+        let Some(rotation) = &self.rotation else {
+            return false;
+        };
+
+        match rotation.max_size_bytes() {
+            Some(Ok(max)) => self.size + incoming as u64 > max,
+            // An invalid `max-size` was already reported as a config
+            // diagnostic at load time; don't rotate on every write for it.
+            Some(Err(_)) => false,
+            None => match rotation {
+                LogRotationConfig::Daily { .. } => self.crossed_boundary(24 * 60 * 60),
+                LogRotationConfig::Hourly { .. } => self.crossed_boundary(60 * 60),
+                LogRotationConfig::MaxSize { .. } => false,
+            },
+        }
+    }
+
+    /// Whether wall-clock time has crossed a `period_secs`-aligned calendar
+    /// boundary (midnight UTC for `Daily`, the top of the hour UTC for
+    /// `Hourly`) since `opened_at`. This is a fixed boundary rather than a
+    /// rolling "`period_secs` since the file was opened" window, so e.g. a
+    /// file opened at 11pm still rotates at the next midnight rather than
+    /// 24 hours later.
+    fn crossed_boundary(&self, period_secs: u64) -> bool {
+        crosses_boundary(period_secs, self.opened_at, SystemTime::now())
+    }
+
+    /// Archived file indices (`.1`, `.2`, ...) currently present on disk,
+    /// lowest (most recent) first.
+    fn present_archive_indices(&self, rotation: &LogRotationConfig) -> Vec<usize> {
+        let mut indices = Vec::new();
+        let mut index = 1;
+        while rotation.rotated_file_name(&self.log_file, index).exists() {
+            indices.push(index);
+            index += 1;
+        }
+        indices
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let Some(rotation) = self.rotation.clone() else {
+            return Ok(());
+        };
+
+        // Shift existing archives up by one index (oldest first) so `.1`
+        // is freed for the file being rotated out now.
+        let mut indices = self.present_archive_indices(&rotation);
+        indices.sort_unstable_by(|a, b| b.cmp(a));
+        for index in indices {
+            fs::rename(
+                rotation.rotated_file_name(&self.log_file, index),
+                rotation.rotated_file_name(&self.log_file, index + 1),
+            )?;
+        }
+
+        let rotated = rotation.rotated_file_name(&self.log_file, 1);
+        if rotation.compress() {
+            let mut input = File::open(&self.log_file)?;
+            let mut encoder = GzEncoder::new(File::create(&rotated)?, Compression::default());
+            io::copy(&mut input, &mut encoder)?;
+            encoder.finish()?;
+            fs::remove_file(&self.log_file)?;
+        } else {
+            fs::rename(&self.log_file, &rotated)?;
+        }
+
+        for index in rotation.archived_files_to_prune(self.present_archive_indices(&rotation)) {
+            let _ = fs::remove_file(rotation.rotated_file_name(&self.log_file, index));
+        }
+
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.log_file)?;
+        self.size = 0;
+        self.opened_at = SystemTime::now();
+        Ok(())
+    }
+}
+
+impl Write for RollingLogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.should_rotate(buf.len()) {
+            self.rotate()?;
         }
+
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
     }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Seconds since the Unix epoch (UTC), saturating to `0` for a `time`
+/// before the epoch. Used to find calendar day/hour boundaries without
+/// pulling in a timezone-aware date/time crate.
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// Whether `to` falls in a later `period_secs`-aligned bucket than `from`,
+/// i.e. a fixed calendar boundary (midnight/top-of-hour UTC) sits between
+/// them, as opposed to merely `to - from >= period_secs`.
+fn crosses_boundary(period_secs: u64, from: SystemTime, to: SystemTime) -> bool {
+    unix_seconds(to) / period_secs > unix_seconds(from) / period_secs
+}
+
+/// Parses a human-readable size like `"10MiB"`, `"512KB"`, or a bare byte
+/// count like `"1048576"` into a number of bytes. Recognizes the binary
+/// (`KiB`/`MiB`/`GiB`) and decimal (`KB`/`MB`/`GB`) suffixes, case-insensitively.
+pub fn parse_human_size(raw: &str) -> Result<u64, String> {
+    let raw = raw.trim();
+    let split_at = raw
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(raw.len());
+    let (number, unit) = raw.split_at(split_at);
+
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid size `{raw}`"))?;
+
+    let multiplier = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1.0,
+        "kb" => 1_000.0,
+        "kib" => 1024.0,
+        "mb" => 1_000_000.0,
+        "mib" => 1024.0 * 1024.0,
+        "gb" => 1_000_000_000.0,
+        "gib" => 1024.0 * 1024.0 * 1024.0,
+        other => return Err(format!("unknown size unit `{other}` in `{raw}`")),
+    };
+
+    Ok((number * multiplier).round() as u64)
 }
 
 /// Cursorword plugin.
@@ -265,7 +1254,7 @@ pub struct PluginConfig {
     pub syntax: SyntaxPluginConfig,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Default)]
 #[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
 pub struct IgnoreConfig {
     /// Whether to ignore the comment line when applicable.
@@ -274,7 +1263,8 @@ pub struct IgnoreConfig {
     /// Only include the results from the files being tracked by git if in a git repo.
     pub git_tracked_only: bool,
 
-    /// Ignore the results from the files whose file name matches this pattern.
+    /// Ignore the results from the files whose file name matches this glob
+    /// pattern, e.g. `*.generated.rs`.
     ///
     /// For instance, if you want to exclude the results whose file name matches
     /// `test` for dumb_jump provider:
@@ -285,8 +1275,68 @@ pub struct IgnoreConfig {
     /// ```
     pub ignore_file_name_pattern: Vec<String>,
 
-    /// Ignore the results from the files whose file path matches this pattern.
+    /// Ignore the results from the files whose file path matches this glob
+    /// pattern, e.g. `**/target/**`.
     pub ignore_file_path_pattern: Vec<String>,
+
+    /// Compiled form of `ignore_file_name_pattern`, built lazily on first use.
+    #[serde(skip)]
+    name_glob_set: OnceCell<GlobSet>,
+
+    /// Compiled form of `ignore_file_path_pattern`, built lazily on first use.
+    #[serde(skip)]
+    path_glob_set: OnceCell<GlobSet>,
+}
+
+impl PartialEq for IgnoreConfig {
+    fn eq(&self, other: &Self) -> bool {
+        self.ignore_comments == other.ignore_comments
+            && self.git_tracked_only == other.git_tracked_only
+            && self.ignore_file_name_pattern == other.ignore_file_name_pattern
+            && self.ignore_file_path_pattern == other.ignore_file_path_pattern
+    }
+}
+
+impl Eq for IgnoreConfig {}
+
+impl IgnoreConfig {
+    /// Returns whether `path` should be ignored: its file name component is
+    /// tested against `ignore_file_name_pattern` and the full path against
+    /// `ignore_file_path_pattern`.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        if let Some(file_name) = path.file_name().and_then(|name| name.to_str()) {
+            if self.name_glob_set().is_match(file_name) {
+                return true;
+            }
+        }
+
+        self.path_glob_set().is_match(path)
+    }
+
+    fn name_glob_set(&self) -> &GlobSet {
+        self.name_glob_set
+            .get_or_init(|| build_glob_set(&self.ignore_file_name_pattern))
+    }
+
+    fn path_glob_set(&self) -> &GlobSet {
+        self.path_glob_set
+            .get_or_init(|| build_glob_set(&self.ignore_file_path_pattern))
+    }
+}
+
+/// Compiles `patterns` into a `GlobSet`. Patterns that fail to parse are
+/// silently dropped here; they're reported as config diagnostics (with the
+/// exact offending pattern) by `validate_ignore_patterns` at load time.
+fn build_glob_set(patterns: &[String]) -> GlobSet {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        if let Ok(glob) = Glob::new(pattern) {
+            builder.add(glob);
+        }
+    }
+    builder
+        .build()
+        .unwrap_or_else(|_| GlobSetBuilder::new().build().expect("empty GlobSet always builds"))
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq)]
@@ -335,9 +1385,19 @@ pub struct ProviderConfig {
     /// "files" = 100
     /// ```
     pub debounce: HashMap<String, u64>,
+
+    /// Per-provider preview overrides, keyed by provider id (`*` is a
+    /// fallback applied to providers without their own entry).
+    ///
+    /// ```toml
+    /// [provider.preview.dumb_jump]
+    /// color-mode = "auto"
+    /// light-color-scheme = "Visual Studio Light+"
+    /// ```
+    pub preview: HashMap<String, ProviderPreviewConfig>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Eq, PartialEq)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub enum HighlightEngine {
     SublimeSyntax,
@@ -346,6 +1406,79 @@ pub enum HighlightEngine {
     Vim,
 }
 
+/// Per-provider override of the global preview highlight engine and color
+/// scheme, with `color-mode` letting a provider adapt to the terminal
+/// background instead of hard-coding one theme.
+#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct ProviderPreviewConfig {
+    /// Overrides the global highlight engine for this provider.
+    pub preview_highlight_engine: Option<HighlightEngine>,
+
+    /// `dark`, `light`, or `auto` to detect the terminal background.
+    pub color_mode: ColorMode,
+
+    /// Color scheme used when the resolved mode is `dark`, falling back
+    /// to the global `sublime-syntax-color-scheme` when unset.
+    pub dark_color_scheme: Option<String>,
+
+    /// Color scheme used when the resolved mode is `light`.
+    pub light_color_scheme: Option<String>,
+}
+
+/// How a provider's preview theme should adapt to the terminal background.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub enum ColorMode {
+    #[default]
+    Dark,
+    Light,
+    /// Detect the background via [`Background::from_colorfgbg`] or
+    /// whatever signal the editor integration reports.
+    Auto,
+}
+
+impl ColorMode {
+    /// Resolves `self` against the detected terminal `background`: `Dark`
+    /// and `Light` are returned outright, `Auto` follows `background`.
+    pub fn resolve(&self, background: Background) -> Background {
+        match self {
+            Self::Dark => Background::Dark,
+            Self::Light => Background::Light,
+            Self::Auto => background,
+        }
+    }
+}
+
+/// Detected (or editor-reported) terminal background, used to resolve
+/// [`ColorMode::Auto`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Background {
+    Dark,
+    Light,
+}
+
+impl Background {
+    /// Parses a `COLORFGBG`-style signal (`"fg;bg"`), where the common
+    /// terminal convention treats a `bg` of `7` or `15` as light and
+    /// everything else as dark.
+    pub fn from_colorfgbg(colorfgbg: &str) -> Self {
+        match colorfgbg.rsplit(';').next().and_then(|bg| bg.parse::<u8>().ok()) {
+            Some(7) | Some(15) => Self::Light,
+            _ => Self::Dark,
+        }
+    }
+}
+
+/// The effective preview configuration for a provider, after applying the
+/// provider's own override (or the `*` fallback entry when it has none)
+/// over the global defaults. See [`Config::preview_config`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ResolvedPreviewConfig {
+    pub highlight_engine: HighlightEngine,
+    pub sublime_syntax_color_scheme: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq)]
 #[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
 pub struct Config {
@@ -390,6 +1523,45 @@ impl Config {
             .copied()
             .unwrap_or(DEFAULT_DEBOUNCE)
     }
+
+    /// Resolves the effective preview configuration for `provider_id`,
+    /// analogous to `ignore_config`/`provider_debounce`: the provider's own
+    /// entry is used, or the `*` entry when the provider has none (the two
+    /// are not combined), and fields unset in that entry take the global
+    /// defaults.
+    /// `background` is the detected terminal background, used only when
+    /// the resolved `color-mode` is `auto`.
+    pub fn preview_config(
+        &self,
+        provider_id: &str,
+        background: Background,
+    ) -> ResolvedPreviewConfig {
+        let overrides = self
+            .provider
+            .preview
+            .get(provider_id)
+            .or_else(|| self.provider.preview.get("*"));
+
+        let highlight_engine = overrides
+            .and_then(|preview| preview.preview_highlight_engine.clone())
+            .unwrap_or_else(|| self.provider.preview_highlight_engine.clone());
+
+        let color_mode = overrides.map_or(ColorMode::default(), |preview| preview.color_mode);
+
+        let sublime_syntax_color_scheme = match color_mode.resolve(background) {
+            Background::Dark => overrides
+                .and_then(|preview| preview.dark_color_scheme.clone())
+                .or_else(|| self.provider.sublime_syntax_color_scheme.clone()),
+            Background::Light => overrides
+                .and_then(|preview| preview.light_color_scheme.clone())
+                .or_else(|| self.provider.sublime_syntax_color_scheme.clone()),
+        };
+
+        ResolvedPreviewConfig {
+            highlight_engine,
+            sublime_syntax_color_scheme,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -470,4 +1642,369 @@ mod tests {
         let config = Config::default();
         toml::to_string_pretty(&config).expect("Deserialize config is okay");
     }
+
+    #[test]
+    fn test_diagnose_typo_suggestion() {
+        let diagnostics = diagnose(
+            r#"
+          [matcher]
+          tiebrake = "score,-begin,-end,-length"
+"#,
+        );
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, "matcher.tiebrake");
+        assert!(diagnostics[0].message.contains("did you mean `tiebreak`?"));
+    }
+
+    #[test]
+    fn test_diagnose_semantic_errors() {
+        let diagnostics = diagnose(
+            r#"
+          [log]
+          max-level = "verbose"
+
+          [matcher]
+          tiebreak = "score,-bogus"
+"#,
+        );
+
+        assert!(diagnostics
+            .iter()
+            .any(|d| d.path == "log.max-level" && d.message.contains("invalid log level")));
+        assert!(diagnostics
+            .iter()
+            .any(|d| d.path == "matcher.tiebreak" && d.message.contains("bogus")));
+    }
+
+    #[test]
+    fn test_diagnose_invalid_glob() {
+        let diagnostics = diagnose(
+            r#"
+          [global-ignore]
+          ignore-file-path-pattern = ["[invalid"]
+"#,
+        );
+
+        assert!(diagnostics
+            .iter()
+            .any(|d| d.path == "global-ignore.ignore-file-path-pattern"
+                && d.message.contains("[invalid")));
+    }
+
+    #[test]
+    fn test_ignore_config_is_ignored() {
+        let ignore = IgnoreConfig {
+            ignore_file_name_pattern: vec!["*.generated.rs".to_string()],
+            ignore_file_path_pattern: vec!["**/target/**".to_string()],
+            ..Default::default()
+        };
+
+        assert!(ignore.is_ignored(Path::new("src/foo.generated.rs")));
+        assert!(ignore.is_ignored(Path::new("target/debug/build.rs")));
+        assert!(!ignore.is_ignored(Path::new("src/main.rs")));
+    }
+
+    #[test]
+    fn test_discover_project_config_file() {
+        let root = std::env::temp_dir().join(format!(
+            "vim-clap-test-discover-{}",
+            std::process::id()
+        ));
+        let nested = root.join("a").join("b");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(root.join(PROJECT_LOCAL_CONFIG_FILE_NAME), "").unwrap();
+
+        assert_eq!(
+            discover_project_config_file(&nested),
+            Some(root.join(PROJECT_LOCAL_CONFIG_FILE_NAME))
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_merge_project_config() {
+        let global = Config {
+            matcher: MatcherConfig {
+                tiebreak: "score,-begin".to_string(),
+            },
+            provider: ProviderConfig {
+                debounce: HashMap::from([("*".to_string(), 200)]),
+                ..Default::default()
+            },
+            global_ignore: IgnoreConfig {
+                ignore_file_path_pattern: vec!["build".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let dir = std::env::temp_dir().join(format!("vim-clap-test-merge-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let local_config_file = dir.join(PROJECT_LOCAL_CONFIG_FILE_NAME);
+        std::fs::write(
+            &local_config_file,
+            r#"
+          [matcher]
+          tiebreak = "score,-length"
+
+          [provider.debounce]
+          "files" = 50
+
+          [global-ignore]
+          ignore-file-path-pattern = ["test"]
+"#,
+        )
+        .unwrap();
+
+        let merged = merge_project_config(&global, &local_config_file).unwrap();
+
+        assert_eq!(merged.matcher.tiebreak, "score,-length");
+        assert_eq!(
+            merged.provider.debounce,
+            HashMap::from([("*".to_string(), 200), ("files".to_string(), 50)])
+        );
+        assert_eq!(
+            merged.global_ignore.ignore_file_path_pattern,
+            vec!["build".to_string(), "test".to_string()]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_merge_project_config_bad_local_file_does_not_lose_global() {
+        let global = Config {
+            matcher: MatcherConfig {
+                tiebreak: "score,-begin".to_string(),
+            },
+            ..Default::default()
+        };
+
+        let dir = std::env::temp_dir().join(format!(
+            "vim-clap-test-merge-bad-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let local_config_file = dir.join(PROJECT_LOCAL_CONFIG_FILE_NAME);
+        // `tiebreak` must be a string, not an integer: this fails to
+        // deserialize back into `Config` once merged.
+        std::fs::write(&local_config_file, "[matcher]\ntiebreak = 5\n").unwrap();
+
+        let err = merge_project_config(&global, &local_config_file)
+            .expect_err("a local file that doesn't match the schema must be rejected");
+        assert!(err.contains(&local_config_file.display().to_string()));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_watch_file_survives_atomic_rename() {
+        let dir = std::env::temp_dir().join(format!("vim-clap-test-watch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let watched = dir.join("watched.toml");
+        std::fs::write(&watched, "before").unwrap();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        watch_file(watched.clone(), move || {
+            let _ = tx.send(());
+        });
+
+        // Give the watcher thread time to attach before the first save.
+        std::thread::sleep(Duration::from_millis(200));
+
+        // Simulate an editor's atomic write-then-rename save.
+        let tmp_file = dir.join("watched.toml.tmp");
+        std::fs::write(&tmp_file, "after").unwrap();
+        std::fs::rename(&tmp_file, &watched).unwrap();
+
+        rx.recv_timeout(Duration::from_secs(5))
+            .expect("watch_file must fire after an atomic rename-based save");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_watch_file_fires_on_create_for_not_yet_existing_file() {
+        // Backs config_for_project's "no .vimclap.toml found yet" path,
+        // which watches `project_dir` itself for one appearing later.
+        let dir = std::env::temp_dir().join(format!(
+            "vim-clap-test-watch-create-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let watched = dir.join(PROJECT_LOCAL_CONFIG_FILE_NAME);
+        assert!(!watched.exists());
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        watch_file(watched.clone(), move || {
+            let _ = tx.send(());
+        });
+
+        // Give the watcher thread time to attach before the file appears.
+        std::thread::sleep(Duration::from_millis(200));
+
+        std::fs::write(&watched, "").unwrap();
+
+        rx.recv_timeout(Duration::from_secs(5))
+            .expect("watch_file must fire once a not-yet-existing file is created");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_human_size() {
+        assert_eq!(parse_human_size("10MiB").unwrap(), 10 * 1024 * 1024);
+        assert_eq!(parse_human_size("10MB").unwrap(), 10_000_000);
+        assert_eq!(parse_human_size("512").unwrap(), 512);
+        assert!(parse_human_size("10XB").is_err());
+    }
+
+    #[test]
+    fn test_log_rotation_deserialize() {
+        let log: LogConfig = toml::from_str(
+            r#"
+          rotation.trigger = "max-size"
+          rotation.max-size = "10MiB"
+          rotation.max-archived-files = 5
+          rotation.compress = true
+"#,
+        )
+        .expect("Failed to deserialize log rotation config");
+
+        let rotation = log.rotation.expect("rotation must be present");
+        assert_eq!(rotation.max_size_bytes(), Some(Ok(10 * 1024 * 1024)));
+        assert_eq!(rotation.max_archived_files(), Some(5));
+        assert!(rotation.compress());
+    }
+
+    #[test]
+    fn test_archived_files_to_prune() {
+        let rotation = LogRotationConfig::Daily {
+            max_archived_files: Some(2),
+            compress: false,
+        };
+
+        assert_eq!(
+            rotation.archived_files_to_prune(vec![1, 2, 3, 4]),
+            vec![3, 4]
+        );
+        assert_eq!(rotation.archived_files_to_prune(vec![1]), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_rolling_log_writer_rotates_and_prunes() {
+        let dir = std::env::temp_dir().join(format!(
+            "vim-clap-test-rolling-log-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let log_file = dir.join("clap.log");
+
+        let rotation = LogRotationConfig::MaxSize {
+            max_size: "16B".to_string(),
+            max_archived_files: Some(1),
+            compress: false,
+        };
+        let mut writer = RollingLogWriter::open(log_file.clone(), Some(rotation)).unwrap();
+
+        writer.write_all(b"first message\n").unwrap();
+        writer.write_all(b"second message\n").unwrap();
+        writer.write_all(b"third message\n").unwrap();
+        writer.flush().unwrap();
+
+        // `log_file` always holds the newest content, and only the most
+        // recent archive survives pruning to `max-archived-files = 1`.
+        assert_eq!(
+            std::fs::read_to_string(&log_file).unwrap(),
+            "third message\n"
+        );
+        assert!(dir.join("clap.log.1").exists());
+        assert!(!dir.join("clap.log.2").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_crosses_boundary_is_calendar_aligned_not_rolling() {
+        const DAY: u64 = 24 * 60 * 60;
+        let epoch = SystemTime::UNIX_EPOCH;
+
+        // Opened at 11pm; one hour later it's the next calendar day, so a
+        // daily rotation must fire despite only 1 hour (not 24) elapsing.
+        let opened_at_11pm = epoch + Duration::from_secs(23 * 60 * 60);
+        let one_hour_later = epoch + Duration::from_secs(24 * 60 * 60);
+        assert!(crosses_boundary(DAY, opened_at_11pm, one_hour_later));
+
+        // Opened at midnight; 10 hours later is still the same calendar
+        // day, so no daily rotation should fire yet.
+        let opened_at_midnight = epoch;
+        let ten_hours_later = epoch + Duration::from_secs(10 * 60 * 60);
+        assert!(!crosses_boundary(DAY, opened_at_midnight, ten_hours_later));
+
+        // A genuine 24h+ gap within the same relative offset still crosses.
+        let two_days_later = epoch + Duration::from_secs(2 * DAY);
+        assert!(crosses_boundary(DAY, opened_at_midnight, two_days_later));
+    }
+
+    #[test]
+    fn test_preview_config_resolution() {
+        let mut config = Config {
+            provider: ProviderConfig {
+                sublime_syntax_color_scheme: Some("Visual Studio Dark+".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        config.provider.preview.insert(
+            "dumb_jump".to_string(),
+            ProviderPreviewConfig {
+                color_mode: ColorMode::Auto,
+                light_color_scheme: Some("Visual Studio Light+".to_string()),
+                ..Default::default()
+            },
+        );
+
+        // No override for "files": falls back to the global scheme.
+        let files = config.preview_config("files", Background::Light);
+        assert_eq!(
+            files.sublime_syntax_color_scheme,
+            Some("Visual Studio Dark+".to_string())
+        );
+
+        // "dumb_jump" is in auto mode and adapts to the detected background.
+        let dumb_jump_dark = config.preview_config("dumb_jump", Background::Dark);
+        assert_eq!(
+            dumb_jump_dark.sublime_syntax_color_scheme,
+            Some("Visual Studio Dark+".to_string())
+        );
+        let dumb_jump_light = config.preview_config("dumb_jump", Background::Light);
+        assert_eq!(
+            dumb_jump_light.sublime_syntax_color_scheme,
+            Some("Visual Studio Light+".to_string())
+        );
+
+        // Override sets only `color-mode`, with no `light-color-scheme` of its own:
+        // still falls back to the global scheme rather than losing highlighting.
+        config.provider.preview.insert(
+            "grep".to_string(),
+            ProviderPreviewConfig {
+                color_mode: ColorMode::Auto,
+                ..Default::default()
+            },
+        );
+        let grep_light = config.preview_config("grep", Background::Light);
+        assert_eq!(
+            grep_light.sublime_syntax_color_scheme,
+            Some("Visual Studio Dark+".to_string())
+        );
+    }
+
+    #[test]
+    fn test_background_from_colorfgbg() {
+        assert_eq!(Background::from_colorfgbg("15;0"), Background::Dark);
+        assert_eq!(Background::from_colorfgbg("0;15"), Background::Light);
+        assert_eq!(Background::from_colorfgbg("bogus"), Background::Dark);
+    }
 }